@@ -0,0 +1,84 @@
+/// A simple MSB-first bit reader over a byte slice.
+///
+/// MP3 side info and main data are both tightly packed bitstreams (fields
+/// are not byte-aligned), so every reader below walks bits rather than
+/// bytes.
+pub struct BitReader<'a> {
+    data: &'a [u8],
+    bit_pos: usize,
+}
+
+impl<'a> BitReader<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        BitReader { data, bit_pos: 0 }
+    }
+
+    /// Reads `n` bits (`n <= 32`) and returns them right-aligned.
+    ///
+    /// Truncated frames (a corrupt or short main-data buffer) can run a
+    /// caller past the end of `data`; bits read past the end come back as
+    /// 0 rather than panicking, same as the rest of this decoder's
+    /// tolerance for malformed input (see `decode_spectrum`'s `end_bit`
+    /// check, which this backs up for callers that don't bound themselves).
+    pub fn read_bits(&mut self, n: u32) -> u32 {
+        let mut result: u32 = 0;
+        for _ in 0..n {
+            let bit = match self.data.get(self.bit_pos / 8) {
+                Some(byte) => (byte >> (7 - (self.bit_pos % 8))) & 1,
+                None => 0,
+            };
+            result = (result << 1) | bit as u32;
+            self.bit_pos += 1;
+        }
+        result
+    }
+
+    /// Reads a single bit as a `bool`.
+    pub fn read_bit(&mut self) -> bool {
+        self.read_bits(1) != 0
+    }
+
+    /// Current position, in bits, from the start of the slice.
+    pub fn bit_pos(&self) -> usize {
+        self.bit_pos
+    }
+
+    /// Number of bits left before the end of the slice.
+    pub fn bits_left(&self) -> usize {
+        self.data.len() * 8 - self.bit_pos
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read_bits_walks_msb_first_across_byte_boundaries() {
+        let mut bits = BitReader::new(&[0b1010_0110, 0b1100_0000]);
+        assert_eq!(bits.read_bits(4), 0b1010);
+        assert_eq!(bits.read_bits(6), 0b011011);
+    }
+
+    #[test]
+    fn read_bit_reads_a_single_bit() {
+        let mut bits = BitReader::new(&[0b1000_0000]);
+        assert!(bits.read_bit());
+        assert!(!bits.read_bit());
+    }
+
+    #[test]
+    fn bits_left_tracks_remaining_bits() {
+        let mut bits = BitReader::new(&[0u8; 2]);
+        assert_eq!(bits.bits_left(), 16);
+        bits.read_bits(5);
+        assert_eq!(bits.bits_left(), 11);
+    }
+
+    #[test]
+    fn read_bits_past_the_end_returns_zero_instead_of_panicking() {
+        let mut bits = BitReader::new(&[0xFF]);
+        bits.read_bits(8);
+        assert_eq!(bits.read_bits(8), 0);
+    }
+}
@@ -0,0 +1,442 @@
+use std::f32::consts::PI;
+
+use crate::sideinfo::{BlockType, Channel, Granule, ScaleFactors};
+
+const SUBBANDS: usize = 32;
+const LINES_PER_SUBBAND: usize = 18;
+const SAMPLES_PER_GRANULE: usize = SUBBANDS * LINES_PER_SUBBAND; // 576
+
+/// Long-block scalefactor band boundaries (21 bands, cumulative line index).
+const LONG_SFB_BOUNDARIES: [usize; 22] = [
+    0, 4, 8, 12, 16, 20, 24, 30, 36, 44, 52, 62, 74, 90, 110, 134, 162, 196, 238, 288, 342, 576,
+];
+
+/// Short-block scalefactor band boundaries (12 bands, per window).
+const SHORT_SFB_BOUNDARIES: [usize; 13] = [0, 4, 8, 12, 16, 22, 30, 40, 52, 66, 84, 106, 192];
+
+lazy_static! {
+    static ref LONG_WINDOW: [f32; 36] = sine_window_36();
+    static ref START_WINDOW: [f32; 36] = transitional_window(true);
+    static ref STOP_WINDOW: [f32; 36] = transitional_window(false);
+    static ref SHORT_WINDOW: [f32; 12] = sine_window_12();
+    static ref ANTIALIAS: [(f32, f32); 8] = antialias_coefficients();
+    static ref SYNTHESIS_WINDOW: [f32; 512] = synthesis_window();
+}
+
+fn sine_window_36() -> [f32; 36] {
+    let mut w = [0f32; 36];
+    for (i, slot) in w.iter_mut().enumerate() {
+        *slot = (PI / 36.0 * (i as f32 + 0.5)).sin();
+    }
+    w
+}
+
+fn sine_window_12() -> [f32; 12] {
+    let mut w = [0f32; 12];
+    for (i, slot) in w.iter_mut().enumerate() {
+        *slot = (PI / 12.0 * (i as f32 + 0.5)).sin();
+    }
+    w
+}
+
+/// The ISO `start`/`stop` transitional windows: a sine half on one side,
+/// flat 1.0 in the middle, and a short sine taper on the other, so a
+/// long block can hand off cleanly to/from a sequence of short blocks.
+fn transitional_window(start: bool) -> [f32; 36] {
+    let mut w = [0f32; 36];
+    if start {
+        for (i, slot) in w.iter_mut().enumerate().take(18) {
+            *slot = (PI / 36.0 * (i as f32 + 0.5)).sin();
+        }
+        for slot in w.iter_mut().take(24).skip(18) {
+            *slot = 1.0;
+        }
+        for (i, slot) in w.iter_mut().enumerate().take(30).skip(24) {
+            *slot = (PI / 12.0 * ((i - 18) as f32 + 0.5)).sin();
+        }
+    } else {
+        for (i, slot) in w.iter_mut().enumerate().take(12).skip(6) {
+            *slot = (PI / 12.0 * ((i - 6) as f32 + 0.5)).sin();
+        }
+        for slot in w.iter_mut().take(18).skip(12) {
+            *slot = 1.0;
+        }
+        for (i, slot) in w.iter_mut().enumerate().skip(18) {
+            *slot = (PI / 36.0 * (i as f32 + 0.5)).sin();
+        }
+    }
+    w
+}
+
+/// Butterfly coefficients for the antialiasing pass applied across every
+/// pair of adjacent (long-block) subbands, derived from the standard
+/// `ci` constants via `cs = 1/sqrt(1+ci^2)`, `ca = ci*cs`.
+fn antialias_coefficients() -> [(f32, f32); 8] {
+    const CI: [f32; 8] = [-0.6, -0.535, -0.33, -0.185, -0.095, -0.041, -0.0142, -0.0037];
+    let mut out = [(0f32, 0f32); 8];
+    for (i, &ci) in CI.iter().enumerate() {
+        let cs = 1.0 / (1.0 + ci * ci).sqrt();
+        out[i] = (cs, ci * cs);
+    }
+    out
+}
+
+/// Synthesis-filterbank window (512 taps), used by `synth_one` to weight
+/// the 16 interleaved 32-sample groups the polyphase filter sums over.
+///
+/// This should be the literal `D[512]` prototype filter from ISO/IEC
+/// 11172-3 Table B.3 (Annex 3-B), which is an empirically optimized table
+/// rather than something derived from a closed-form equation. Without a
+/// machine-readable copy of the standard on hand in this sandbox to
+/// transcribe the real 512 coefficients, this instead generates a
+/// windowed-sinc low-pass (the same family of filter the real table
+/// belongs to, and symmetric the same way: `d[i] == d[511 - i]`), so decoded
+/// PCM will be in the right ballpark but not bit-exact against a reference
+/// decoder. Swap this out for the real table's values if exact parity is
+/// ever needed.
+fn synthesis_window() -> [f32; 512] {
+    let mut d = [0f32; 512];
+    for (i, slot) in d.iter_mut().enumerate() {
+        // Centered on 255.5 (the midpoint between taps 255 and 256) so both
+        // factors come out symmetric: `d[i] == d[511 - i]`, same as the real
+        // table's linear-phase FIR design.
+        let x = i as f32 - 255.5;
+        let sinc = (PI * x / 32.0).sin() / (PI * x / 32.0);
+        let hann = 0.5 - 0.5 * (2.0 * PI * (i as f32 + 0.5) / 512.0).cos();
+        *slot = sinc * hann;
+    }
+    d
+}
+
+/// Requantizes one granule/channel's 576 Huffman-decoded lines into real
+/// (floating point) frequency-domain samples, applying `global_gain`, the
+/// per-band scalefactor (scaled ×1 or ×2 per `scalefac_scale`), and for
+/// short/mixed windows the block's own `subblock_gain` on top.
+pub fn requantize(channel: &Channel, spectrum: &[i32; 576]) -> [f32; 576] {
+    let mut xr = [0f32; 576];
+    let scale_mul = if channel.scalefac_scale { 2.0 } else { 1.0 };
+    let gain = (channel.global_gain as f32 - 210.0) / 4.0;
+
+    match &channel.scalefactors {
+        ScaleFactors::Long(sf) => {
+            for band in 0..21 {
+                let (start, end) = (LONG_SFB_BOUNDARIES[band], LONG_SFB_BOUNDARIES[band + 1]);
+                let exponent = gain - scale_mul * sf[band] as f32;
+                let factor = 2f32.powf(exponent);
+                for i in start..end {
+                    xr[i] = dequantize(spectrum[i]) * factor;
+                }
+            }
+        }
+        ScaleFactors::Short(sf) => {
+            // Each window covers the same 192-line span as the other two,
+            // offset by `window * 192`.
+            for band in 0..12 {
+                let (start, end) = (SHORT_SFB_BOUNDARIES[band], SHORT_SFB_BOUNDARIES[band + 1]);
+                for (window, &sf_window) in sf[band].iter().enumerate() {
+                    let subblock_gain = channel.subblock_gain[window] as f32;
+                    let exponent = gain - scale_mul * sf_window as f32 - 2.0 * subblock_gain;
+                    let factor = 2f32.powf(exponent);
+                    for i in start..end {
+                        let idx = window * 192 + i;
+                        xr[idx] = dequantize(spectrum[idx]) * factor;
+                    }
+                }
+            }
+        }
+        ScaleFactors::Mixed { long, short } => {
+            for band in 0..8 {
+                let (start, end) = (LONG_SFB_BOUNDARIES[band], LONG_SFB_BOUNDARIES[band + 1]);
+                let exponent = gain - scale_mul * long[band] as f32;
+                let factor = 2f32.powf(exponent);
+                for i in start..end.min(576) {
+                    xr[i] = dequantize(spectrum[i]) * factor;
+                }
+            }
+            let mixed_start = LONG_SFB_BOUNDARIES[8];
+            for (band, windows) in short.iter().enumerate() {
+                for (window, &sf) in windows.iter().enumerate() {
+                    let subblock_gain = channel.subblock_gain[window] as f32;
+                    let exponent = gain - scale_mul * sf as f32 - 2.0 * subblock_gain;
+                    let factor = 2f32.powf(exponent);
+                    let idx = mixed_start + band * 3 + window;
+                    if idx < 576 {
+                        xr[idx] = dequantize(spectrum[idx]) * factor;
+                    }
+                }
+            }
+        }
+    }
+
+    xr
+}
+
+fn dequantize(value: i32) -> f32 {
+    let magnitude = (value.unsigned_abs() as f32).powf(4.0 / 3.0);
+    if value < 0 {
+        -magnitude
+    } else {
+        magnitude
+    }
+}
+
+/// Per-channel state carried across frames: the previous granule's IMDCT
+/// overlap tail (18 samples per subband) and the synthesis filterbank's
+/// 1024-entry V history.
+pub struct ChannelState {
+    overlap: [[f32; 18]; SUBBANDS],
+    v_fifo: Vec<[f32; 64]>,
+}
+
+impl ChannelState {
+    pub fn new() -> Self {
+        ChannelState { overlap: [[0.0; 18]; SUBBANDS], v_fifo: vec![[0.0; 64]; 16] }
+    }
+}
+
+impl Default for ChannelState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn window_for(block_type: &BlockType) -> &'static [f32; 36] {
+    match block_type {
+        BlockType::Normal => &LONG_WINDOW,
+        BlockType::Start | BlockType::Mixed => &START_WINDOW,
+        BlockType::End => &STOP_WINDOW,
+        BlockType::Short => &LONG_WINDOW, // short subbands are windowed per-window instead
+    }
+}
+
+/// 18-point IMDCT (long blocks): 18 frequency-domain coefficients in, 36
+/// windowed time-domain samples out.
+fn imdct_long(coeffs: &[f32], window: &[f32; 36]) -> [f32; 36] {
+    let mut out = [0f32; 36];
+    for (i, slot) in out.iter_mut().enumerate() {
+        let mut sum = 0f32;
+        for (k, &c) in coeffs.iter().enumerate().take(18) {
+            let angle = PI / 72.0 * (2.0 * i as f32 + 1.0 + 18.0) * (2.0 * k as f32 + 1.0);
+            sum += c * angle.cos();
+        }
+        *slot = sum * window[i];
+    }
+    out
+}
+
+/// 6-point IMDCT (one window of a short block): 6 coefficients in, 12
+/// windowed time-domain samples out.
+fn imdct_short(coeffs: &[f32]) -> [f32; 12] {
+    let mut out = [0f32; 12];
+    for (i, slot) in out.iter_mut().enumerate() {
+        let mut sum = 0f32;
+        for (k, &c) in coeffs.iter().enumerate().take(6) {
+            let angle = PI / 24.0 * (2.0 * i as f32 + 1.0 + 6.0) * (2.0 * k as f32 + 1.0);
+            sum += c * angle.cos();
+        }
+        *slot = sum * SHORT_WINDOW[i];
+    }
+    out
+}
+
+/// Runs the hybrid synthesis back end (antialias + IMDCT/overlap-add +
+/// polyphase filterbank) for one granule of one channel, returning the
+/// granule's 576 PCM samples.
+fn synth_granule(channel: &Channel, xr: &[f32; 576], state: &mut ChannelState) -> [f32; 576] {
+    let mut subband_lines: Vec<[f32; 18]> = (0..SUBBANDS)
+        .map(|sb| {
+            let mut lines = [0f32; 18];
+            if matches!(channel.block_type, BlockType::Short) {
+                // Short-block `xr` is window-major (see `requantize`): window
+                // `w`'s 6 lines for subband `sb` sit at `w * 192 + sb * 6`.
+                for w in 0..3 {
+                    let start = w * 192 + sb * 6;
+                    lines[w * 6..w * 6 + 6].copy_from_slice(&xr[start..start + 6]);
+                }
+            } else {
+                lines.copy_from_slice(&xr[sb * 18..sb * 18 + 18]);
+            }
+            lines
+        })
+        .collect();
+
+    // Antialiasing butterflies run across every adjacent pair of subbands;
+    // short blocks skip it since their 18 "lines" are really 3 windows.
+    if !matches!(channel.block_type, BlockType::Short) {
+        for sb in 0..SUBBANDS - 1 {
+            for i in 0..8 {
+                let (cs, ca) = ANTIALIAS[i];
+                let a = subband_lines[sb][17 - i];
+                let b = subband_lines[sb + 1][i];
+                subband_lines[sb][17 - i] = a * cs - b * ca;
+                subband_lines[sb + 1][i] = b * cs + a * ca;
+            }
+        }
+    }
+
+    // Each subband's 36-sample IMDCT output overlap-adds with the tail
+    // stored from the previous granule, leaving one 18-sample time-domain
+    // block per subband for this granule.
+    let mut subband_time = [[0f32; 18]; SUBBANDS];
+    for (sb, lines) in subband_lines.iter().enumerate() {
+        let block = match channel.block_type {
+            BlockType::Short => {
+                let mut combined = [0f32; 36];
+                for w in 0..3 {
+                    let windowed = imdct_short(&lines[w * 6..w * 6 + 6]);
+                    for (i, &v) in windowed.iter().enumerate() {
+                        combined[w * 6 + i] += v;
+                    }
+                }
+                combined
+            }
+            ref block_type => imdct_long(lines, window_for(block_type)),
+        };
+
+        for i in 0..18 {
+            subband_time[sb][i] = block[i] + state.overlap[sb][i];
+            state.overlap[sb][i] = block[18 + i];
+        }
+    }
+
+    // The polyphase filter mixes all 32 subbands together at each of the
+    // 18 sample-times, so it runs per time-step rather than per subband.
+    let mut pcm = [0f32; 576];
+    for t in 0..18 {
+        let mut subbands_at_t = [0f32; SUBBANDS];
+        for sb in 0..SUBBANDS {
+            subbands_at_t[sb] = subband_time[sb][t];
+        }
+        let samples = synth_one(&mut state.v_fifo, &subbands_at_t);
+        pcm[t * SUBBANDS..t * SUBBANDS + SUBBANDS].copy_from_slice(&samples);
+    }
+
+    pcm
+}
+
+/// One polyphase synthesis step: 32 subband values in, 32 PCM samples out.
+fn synth_one(v_fifo: &mut Vec<[f32; 64]>, subbands: &[f32; SUBBANDS]) -> [f32; SUBBANDS] {
+    let mut v = [0f32; 64];
+    for (i, slot) in v.iter_mut().enumerate() {
+        let mut sum = 0f32;
+        for (k, &s) in subbands.iter().enumerate() {
+            let angle = ((16 + i) * (2 * k + 1)) as f32 * PI / 64.0;
+            sum += angle.cos() * s;
+        }
+        *slot = sum;
+    }
+    v_fifo.pop();
+    v_fifo.insert(0, v);
+
+    let mut u = [0f32; 512];
+    for j in 0..8 {
+        for i in 0..32 {
+            u[64 * j + i] = v_fifo[2 * j][i];
+            u[64 * j + 32 + i] = v_fifo[2 * j + 1][32 + i];
+        }
+    }
+
+    let mut out = [0f32; SUBBANDS];
+    for (i, slot) in out.iter_mut().enumerate() {
+        let mut sum = 0f32;
+        for j in 0..16 {
+            sum += u[i + 32 * j] * SYNTHESIS_WINDOW[i + 32 * j];
+        }
+        *slot = sum;
+    }
+    out
+}
+
+/// Decodes a full frame's PCM samples (per channel) from each channel's
+/// granules of requantized spectral lines (`spectra[channel][granule]`),
+/// carrying IMDCT/filterbank state across frames via `state`. MPEG-1
+/// frames carry two granules (1152 samples/channel); MPEG-2/2.5 carry one
+/// (576 samples/channel). `granules[g].channels[ch]` gives each granule its
+/// own `Channel` (block type, window, antialias flag), since granule 0 and
+/// granule 1 commonly differ, e.g. a long block followed by a short one.
+pub fn decode_frame_pcm(
+    granules: &[Granule],
+    spectra: &[Vec<[f32; 576]>],
+    state: &mut [ChannelState],
+) -> Vec<Vec<f32>> {
+    (0..spectra.len())
+        .map(|ch| {
+            let mut out = vec![0f32; spectra[ch].len() * SAMPLES_PER_GRANULE];
+            for (granule, xr) in spectra[ch].iter().enumerate() {
+                let channel = &granules[granule].channels[ch];
+                let pcm = synth_granule(channel, xr, &mut state[ch]);
+                out[granule * SAMPLES_PER_GRANULE..(granule + 1) * SAMPLES_PER_GRANULE]
+                    .copy_from_slice(&pcm);
+            }
+            out
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn synthesis_window_is_symmetric_around_its_midpoint() {
+        let d = synthesis_window();
+        for i in 0..512 {
+            assert!((d[i] - d[511 - i]).abs() < 1e-6, "d[{}] != d[{}]", i, 511 - i);
+        }
+    }
+
+    #[test]
+    fn synthesis_window_peaks_at_its_center() {
+        let d = synthesis_window();
+        let center = d[255].max(d[256]);
+        for &v in d.iter() {
+            assert!(v <= center + 1e-6);
+        }
+    }
+
+    #[test]
+    fn transitional_window_start_holds_the_long_block_plateau() {
+        let w = transitional_window(true);
+        for &v in &w[18..24] {
+            assert_eq!(v, 1.0);
+        }
+    }
+
+    #[test]
+    fn transitional_window_stop_holds_the_long_block_plateau() {
+        let w = transitional_window(false);
+        for &v in &w[12..18] {
+            assert_eq!(v, 1.0);
+        }
+    }
+
+    #[test]
+    fn requantize_applies_subblock_gain_to_short_blocks() {
+        let mut spectrum = [0i32; 576];
+        spectrum[0] = 4;
+        let mut channel = short_channel([0; 3]);
+        let plain = requantize(&channel, &spectrum);
+        channel.subblock_gain = [1, 0, 0];
+        let attenuated = requantize(&channel, &spectrum);
+        // subblock_gain of 1 on window 0 should scale that line by 2^-2.
+        assert!((attenuated[0] - plain[0] / 4.0).abs() < 1e-3, "{} vs {}", attenuated[0], plain[0]);
+    }
+
+    fn short_channel(subblock_gain: [u8; 3]) -> Channel {
+        Channel {
+            part2_3_length: 0,
+            big_values: 0,
+            global_gain: 210,
+            scalefac_compress: 0,
+            preemphasis: false,
+            scalefac_scale: false,
+            count1table_select: false,
+            table_select: [0; 3],
+            region_0_count: 0,
+            region_1_count: 0,
+            block_type: BlockType::Short,
+            subblock_gain,
+            scalefactors: ScaleFactors::Short([[0; 3]; 12]),
+        }
+    }
+}
@@ -0,0 +1,266 @@
+use std::io::{Error, ErrorKind, Read, Result};
+
+use crate::sideinfo;
+use crate::{has_sync_word, new_frame_header, Frame};
+
+/// `"ID3"` magic that opens a leading ID3v2 tag, if one is present.
+const ID3_MAGIC: &[u8; 3] = b"ID3";
+
+/// How `Mp3Frames` reacts to a protected frame whose CRC doesn't match.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CrcMode {
+    /// Yield the frame regardless; `Frame::crc_ok` reports the outcome.
+    Ignore,
+    /// Silently drop frames that fail the CRC check.
+    Skip,
+    /// Return an `Err` for frames that fail the CRC check.
+    Error,
+}
+
+/// Streams `Frame`s out of an MPEG audio bitstream.
+///
+/// Unlike reading a fixed number of frames at a known offset, this walks
+/// an arbitrary `Read` until EOF: it skips a leading ID3v2 tag, and if the
+/// next 4 bytes don't look like a real frame header it scans forward byte
+/// by byte for the next one instead of giving up, so a stream that starts
+/// with metadata or has a corrupt frame in the middle still decodes.
+pub struct Mp3Frames<R> {
+    reader: R,
+    pending: Vec<u8>,
+    skipped_id3: bool,
+    crc_mode: CrcMode,
+}
+
+impl<R: Read> Mp3Frames<R> {
+    pub fn new(reader: R) -> Self {
+        Mp3Frames { reader, pending: Vec::new(), skipped_id3: false, crc_mode: CrcMode::Ignore }
+    }
+
+    /// Sets how frames that fail their CRC-16 check are handled. Defaults
+    /// to `CrcMode::Ignore`.
+    pub fn with_crc_mode(mut self, mode: CrcMode) -> Self {
+        self.crc_mode = mode;
+        self
+    }
+
+    /// Reads one byte, preferring anything left over in `pending` first.
+    fn read_byte(&mut self) -> Result<Option<u8>> {
+        if !self.pending.is_empty() {
+            return Ok(Some(self.pending.remove(0)));
+        }
+        let mut b = [0u8; 1];
+        match self.reader.read(&mut b)? {
+            0 => Ok(None),
+            _ => Ok(Some(b[0])),
+        }
+    }
+
+    /// Skips a leading `"ID3"` tag (magic + version + flags + syncsafe
+    /// 28-bit size). Bytes read that turn out not to be a tag are stashed
+    /// in `pending` so the header scan below still sees them.
+    fn skip_id3v2(&mut self) -> Result<()> {
+        let mut tag = [0u8; 10];
+        let mut filled = 0;
+        while filled < tag.len() {
+            match self.read_byte()? {
+                Some(b) => {
+                    tag[filled] = b;
+                    filled += 1;
+                }
+                None => {
+                    self.pending = tag[..filled].to_vec();
+                    return Ok(());
+                }
+            }
+        }
+        if &tag[0..3] != ID3_MAGIC {
+            self.pending = tag.to_vec();
+            return Ok(());
+        }
+        let size = ((tag[6] as u32 & 0x7f) << 21)
+            | ((tag[7] as u32 & 0x7f) << 14)
+            | ((tag[8] as u32 & 0x7f) << 7)
+            | (tag[9] as u32 & 0x7f);
+        let mut body = vec![0u8; size as usize];
+        self.reader.read_exact(&mut body)?;
+        Ok(())
+    }
+
+    /// Scans forward for the next plausible frame header, sliding the
+    /// 4-byte window one byte at a time past anything that doesn't pan out.
+    fn next_header_bytes(&mut self) -> Result<Option<[u8; 4]>> {
+        let mut window = [0u8; 4];
+        let mut filled = 0;
+        loop {
+            if filled < 4 {
+                match self.read_byte()? {
+                    Some(b) => {
+                        window[filled] = b;
+                        filled += 1;
+                    }
+                    None => return Ok(None),
+                }
+                continue;
+            }
+            if has_sync_word(&window) && plausible_header(&window) {
+                return Ok(Some(window));
+            }
+            window[0] = window[1];
+            window[1] = window[2];
+            window[2] = window[3];
+            match self.read_byte()? {
+                Some(b) => window[3] = b,
+                None => return Ok(None),
+            }
+        }
+    }
+}
+
+/// Beyond the sync word, a handful of reserved field values can never
+/// appear in a real header; ruling them out keeps resync from locking onto
+/// two stray `0xFF` bytes inside unrelated frame data. This has to mirror
+/// every field `new_frame_header` panics on (version, layer, bitrate,
+/// sampling rate), or a plausible-looking header full of reserved values
+/// reaches it and panics instead of being skipped.
+fn plausible_header(header: &[u8; 4]) -> bool {
+    let version_id = (header[1] & 0b00011000) >> 3;
+    let layer_id = (header[1] & 0b00000110) >> 1;
+    let bitrate_index = (header[2] & 0b11110000) >> 4;
+    let sampling_index = (header[2] & 0b00001100) >> 2;
+    version_id != 0b01
+        && layer_id != 0b00
+        && bitrate_index != 0
+        && bitrate_index != 0b1111
+        && sampling_index != 0b11
+}
+
+/// MPEG's CRC-16: polynomial 0x8005, MSB-first, no input/output reflection.
+/// Chaining calls (feeding one call's result in as the next's `crc`) lets
+/// the header and side-info bytes be hashed as if they were one buffer.
+fn crc16(mut crc: u16, data: &[u8]) -> u16 {
+    for &byte in data {
+        crc ^= (byte as u16) << 8;
+        for _ in 0..8 {
+            crc = if crc & 0x8000 != 0 { (crc << 1) ^ 0x8005 } else { crc << 1 };
+        }
+    }
+    crc
+}
+
+/// Checks a protected frame's CRC, computed over the last 2 header bytes
+/// plus the side-info bytes (both already in hand: `header_bytes` and the
+/// front of `body`). Returns `None` if the frame isn't protected.
+fn check_crc(header_bytes: &[u8; 4], header: &crate::FrameHeader, body: &[u8]) -> Option<bool> {
+    if !header.protection {
+        return None;
+    }
+    const CRC_LEN: usize = 2;
+    if body.len() < CRC_LEN {
+        return Some(false);
+    }
+    let expected = ((body[0] as u16) << 8) | body[1] as u16;
+    let side_info_len = sideinfo::side_info_len(&header.id, header.single_channel());
+    let end = (CRC_LEN + side_info_len).min(body.len());
+    let crc = crc16(0xFFFF, &header_bytes[2..4]);
+    let crc = crc16(crc, &body[CRC_LEN..end]);
+    Some(crc == expected)
+}
+
+impl<R: Read> Iterator for Mp3Frames<R> {
+    type Item = Result<Frame>;
+
+    fn next(&mut self) -> Option<Result<Frame>> {
+        if !self.skipped_id3 {
+            self.skipped_id3 = true;
+            if let Err(e) = self.skip_id3v2() {
+                return Some(Err(e));
+            }
+        }
+
+        loop {
+            let header_bytes = match self.next_header_bytes() {
+                Ok(Some(bytes)) => bytes,
+                Ok(None) => return None,
+                Err(e) => return Some(Err(e)),
+            };
+            let header = new_frame_header(&header_bytes);
+            let mut body = vec![0u8; header.size - 4];
+            let from_pending = self.pending.len().min(body.len());
+            body[..from_pending].copy_from_slice(&self.pending[..from_pending]);
+            self.pending.drain(..from_pending);
+            if let Err(e) = self.reader.read_exact(&mut body[from_pending..]) {
+                return Some(Err(e));
+            }
+
+            let crc_ok = check_crc(&header_bytes, &header, &body);
+            if crc_ok == Some(false) {
+                match self.crc_mode {
+                    CrcMode::Skip => continue,
+                    CrcMode::Error => {
+                        return Some(Err(Error::new(ErrorKind::InvalidData, "MP3 frame failed CRC check")))
+                    }
+                    CrcMode::Ignore => {}
+                }
+            }
+            return Some(Ok(Frame { header, body, crc_ok }));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    #[test]
+    fn crc16_of_empty_data_leaves_the_running_crc_unchanged() {
+        assert_eq!(crc16(0xFFFF, &[]), 0xFFFF);
+    }
+
+    #[test]
+    fn crc16_is_order_sensitive() {
+        assert_ne!(crc16(0xFFFF, &[0x01, 0x02]), crc16(0xFFFF, &[0x02, 0x01]));
+    }
+
+    #[test]
+    fn plausible_header_rejects_reserved_bitrate_and_sampling_indexes() {
+        assert!(!plausible_header(&[0xFF, 0xFB, 0b0000_0000, 0x00])); // bitrate index 0 ("free")
+        assert!(!plausible_header(&[0xFF, 0xFB, 0b1111_0000, 0x00])); // bitrate index 0b1111
+        assert!(!plausible_header(&[0xFF, 0xFB, 0b1001_1100, 0x00])); // sampling index 0b11
+        assert!(plausible_header(&[0xFF, 0xFB, 0b1001_0000, 0x00]));
+    }
+
+    #[test]
+    fn plausible_header_rejects_reserved_version_and_layer() {
+        assert!(!plausible_header(&[0xFF, 0b1110_1011, 0b1001_0000, 0x00])); // version id 0b01 (reserved)
+        assert!(!plausible_header(&[0xFF, 0b1111_1001, 0b1001_0000, 0x00])); // layer id 0b00 (reserved)
+        assert!(plausible_header(&[0xFF, 0b1111_1011, 0b1001_0000, 0x00]));
+    }
+
+    #[test]
+    fn skips_leading_id3v2_tag_before_the_first_frame() {
+        // A 10-byte ID3v2 header (magic + version + flags + zero-size tag)
+        // followed directly by a minimal MPEG-1 Layer III frame header.
+        let mut stream = vec![b'I', b'D', b'3', 3, 0, 0, 0, 0, 0, 0];
+        stream.extend_from_slice(&[0xFF, 0xFB, 0x90, 0x00]);
+        stream.resize(stream.len() + 417 - 4, 0);
+
+        let mut frames = Mp3Frames::new(Cursor::new(stream));
+        let frame = frames.next().expect("one frame").expect("frame reads cleanly");
+        assert_eq!(frame.header.sampling_freq, 44100);
+    }
+
+    #[test]
+    fn resyncs_past_garbage_before_a_frame_header() {
+        // 10 zero bytes so skip_id3v2's tag-sized lookahead fully drains
+        // (and isn't mistaken for an ID3 tag) before the real header starts.
+        let mut stream = vec![0u8; 10];
+        stream.extend_from_slice(&[0xFF, 0xFB, 0x90, 0x00]);
+        stream.resize(stream.len() + 417 - 4, 0);
+
+        let mut frames = Mp3Frames::new(Cursor::new(stream));
+        let frame = frames.next().expect("one frame").expect("frame reads cleanly");
+        assert_eq!(frame.header.bitrate, 128);
+    }
+}
@@ -0,0 +1,366 @@
+use std::collections::HashMap;
+use std::io::{Error, ErrorKind, Result};
+
+use crate::bitstream::BitReader;
+use crate::sideinfo::{self, BlockType, Channel, ScaleFactors, SideInfo};
+
+/// Table 1's exact ISO/IEC 11172-3 Table B.7 code lengths, in row-major
+/// `(x, y)` order (`x` outer, `y` inner, both `0..xlen`). This is the one
+/// big-values table small enough (4 symbols) to reproduce byte-for-byte
+/// from the published standard without a machine-readable copy of the spec
+/// on hand. Tables 2 and up run into the hundreds of codewords each and
+/// cannot be transcribed reliably from memory alone, so `decode_spectrum`
+/// refuses to decode them rather than invent data that would silently
+/// desync the bitstream and produce wrong coefficients for every real
+/// stream that uses them (which is nearly all of them).
+const TABLE_1_LENGTHS: [u32; 4] = [1, 3, 2, 3];
+
+lazy_static! {
+    static ref HUFFMAN_1: HuffTable = HuffTable::table_1();
+    static ref COUNT1_TABLE_B: Count1Table = Count1Table::fixed();
+}
+
+/// Turns a set of code lengths into canonical codes (shortest codes first,
+/// ties broken by symbol index), the same scheme DEFLATE/Huffman tables use.
+fn canonical_codes(lengths: &[u32]) -> Vec<(u32, u32)> {
+    let max_len = *lengths.iter().max().unwrap() as usize;
+    let mut count_per_len = vec![0u32; max_len + 1];
+    for &len in lengths {
+        count_per_len[len as usize] += 1;
+    }
+    let mut next_code = vec![0u32; max_len + 2];
+    let mut code = 0u32;
+    for len in 1..=max_len {
+        code = (code + count_per_len[len - 1]) << 1;
+        next_code[len] = code;
+    }
+    let mut order: Vec<usize> = (0..lengths.len()).collect();
+    order.sort_by_key(|&i| (lengths[i], i));
+    let mut result = vec![(0u32, 0u32); lengths.len()];
+    for i in order {
+        let len = lengths[i] as usize;
+        result[i] = (next_code[len], lengths[i]);
+        next_code[len] += 1;
+    }
+    result
+}
+
+/// A big-values region Huffman table: decodes a code into an `(x, y)` pair
+/// of quantized magnitudes (sign and `linbits` escape bits are handled by
+/// the caller, since they depend on the decoded magnitude).
+struct HuffTable {
+    xlen: u8,
+    linbits: u32,
+    max_len: u32,
+    codes: HashMap<(u32, u32), (u8, u8)>,
+}
+
+impl HuffTable {
+    /// The real ISO/IEC 11172-3 Table B.7 codebook for big-values table 1
+    /// (`xlen` 2, `linbits` 0) — see `TABLE_1_LENGTHS` for why this is the
+    /// only big-values table reproduced here.
+    fn table_1() -> Self {
+        let symbols = [(0u8, 0u8), (0, 1), (1, 0), (1, 1)];
+        let codes_by_symbol = canonical_codes(&TABLE_1_LENGTHS);
+
+        let mut codes = HashMap::with_capacity(symbols.len());
+        let mut max_len = 0;
+        for (&(x, y), &(code, len)) in symbols.iter().zip(codes_by_symbol.iter()) {
+            // Table B.7's table 1 codewords (`1`, `001`, `01`, `000`) are the
+            // bitwise complement of the canonical codes for these lengths.
+            let code = !code & ((1 << len) - 1);
+            codes.insert((len, code), (x, y));
+            max_len = max_len.max(len);
+        }
+
+        HuffTable { xlen: 2, linbits: 0, max_len, codes }
+    }
+
+    fn decode(&self, bits: &mut BitReader) -> (u8, u8) {
+        let mut code = 0u32;
+        for len in 1..=self.max_len {
+            code = (code << 1) | bits.read_bits(1);
+            if let Some(&xy) = self.codes.get(&(len, code)) {
+                return xy;
+            }
+        }
+        panic!("no matching Huffman code found");
+    }
+}
+
+/// The count1 region's quadruple tables: decodes a code into four `(v, w,
+/// x, y)` flags, each either 0 or ±1 (sign bits follow for the set ones).
+struct Count1Table {
+    max_len: u32,
+    codes: HashMap<(u32, u32), (bool, bool, bool, bool)>,
+}
+
+impl Count1Table {
+    /// The real standard "Table B": count1 quadruples with no entropy
+    /// coding at all, just the 4 raw bits of `(v, w, x, y)` read back
+    /// verbatim (`hlen` 4, `hcod` equal to the quadruple's bit pattern).
+    /// Table A, the actual Huffman-compressed count1 code, is a published
+    /// 16-entry table like the rest of Table B.7 and is not reproduced
+    /// here for the same reason big-values tables 2 and up aren't (see
+    /// `TABLE_1_LENGTHS`).
+    fn fixed() -> Self {
+        let mut codes = HashMap::with_capacity(16);
+        for i in 0u32..16 {
+            let vwxy = (i & 0b1000 != 0, i & 0b0100 != 0, i & 0b0010 != 0, i & 0b0001 != 0);
+            codes.insert((4, i), vwxy);
+        }
+        Count1Table { max_len: 4, codes }
+    }
+
+    fn decode(&self, bits: &mut BitReader) -> (bool, bool, bool, bool) {
+        let mut code = 0u32;
+        for len in 1..=self.max_len {
+            code = (code << 1) | bits.read_bits(1);
+            if let Some(&vwxy) = self.codes.get(&(len, code)) {
+                return vwxy;
+            }
+        }
+        panic!("no matching count1 Huffman code found");
+    }
+}
+
+/// Region boundaries (in big-values pairs) for the big-values tables.
+/// Only fixed-block granules carry `region_0_count`/`region_1_count`, which
+/// split the big-values area into three regions; window-switching granules
+/// (start, short, mixed and stop blocks) split it in two at line 36.
+fn region_boundaries(channel: &Channel) -> (usize, usize) {
+    match channel.block_type {
+        BlockType::Normal => {
+            let region0 = (channel.region_0_count as usize + 1) * 4;
+            let region1 = region0 + (channel.region_1_count as usize + 1) * 4;
+            (region0, region1)
+        }
+        BlockType::Start | BlockType::End | BlockType::Short | BlockType::Mixed => {
+            (18, channel.big_values as usize)
+        }
+    }
+}
+
+fn decode_big_value(bits: &mut BitReader, table: &HuffTable) -> (i32, i32) {
+    let (x, y) = table.decode(bits);
+    let mut x = x as i32;
+    let mut y = y as i32;
+    if x == table.xlen as i32 - 1 && table.linbits > 0 {
+        x += bits.read_bits(table.linbits) as i32;
+    }
+    if x > 0 && bits.read_bit() {
+        x = -x;
+    }
+    if y == table.xlen as i32 - 1 && table.linbits > 0 {
+        y += bits.read_bits(table.linbits) as i32;
+    }
+    if y > 0 && bits.read_bit() {
+        y = -y;
+    }
+    (x, y)
+}
+
+fn read_count1_value(bits: &mut BitReader, set: bool) -> i32 {
+    if !set {
+        return 0;
+    }
+    if bits.read_bit() {
+        -1
+    } else {
+        1
+    }
+}
+
+/// Huffman-decodes one granule/channel's 576 quantized frequency lines:
+/// the big-values region (two or three tables, picked via `region_0_count`/
+/// `region_1_count`), then the count1 region of ±1 quadruples, stopping at
+/// `end_bit`. Anything left over is the "rzero" region and stays zero.
+///
+/// Only big-values table 1 and count1 table B are bundled (see
+/// `TABLE_1_LENGTHS`/`Count1Table::fixed`); a frame that selects any other
+/// table is not something this decoder can decode correctly yet, so this
+/// returns an error rather than guessing at the bitstream layout.
+fn decode_spectrum(bits: &mut BitReader, channel: &Channel, end_bit: usize) -> Result<[i32; 576]> {
+    let mut out = [0i32; 576];
+    let (region0_end, region1_end) = region_boundaries(channel);
+
+    let mut idx = 0;
+    let mut pair = 0;
+    while pair < channel.big_values as usize && idx + 1 < out.len() && bits.bit_pos() < end_bit {
+        let region = if pair < region0_end {
+            0
+        } else if pair < region1_end {
+            1
+        } else {
+            2
+        };
+        match channel.table_select[region] {
+            1 => {
+                let (x, y) = decode_big_value(bits, &HUFFMAN_1);
+                out[idx] = x;
+                out[idx + 1] = y;
+            }
+            0 | 4 | 14 => {} // reserved/unused table indices carry no coefficients
+            n => {
+                return Err(Error::new(
+                    ErrorKind::Unsupported,
+                    format!(
+                        "big-values Huffman table {n} is not implemented (only table 1 is); \
+                         this decoder's Huffman stage is unfinished"
+                    ),
+                ))
+            }
+        }
+        idx += 2;
+        pair += 1;
+    }
+
+    let count1_table = if channel.count1table_select {
+        &*COUNT1_TABLE_B
+    } else {
+        return Err(Error::new(
+            ErrorKind::Unsupported,
+            "count1 Huffman table A is not implemented (only table B is); this decoder's \
+             Huffman stage is unfinished",
+        ));
+    };
+    while bits.bit_pos() < end_bit && idx + 3 < out.len() {
+        let (v, w, x, y) = count1_table.decode(bits);
+        out[idx] = read_count1_value(bits, v);
+        out[idx + 1] = read_count1_value(bits, w);
+        out[idx + 2] = read_count1_value(bits, x);
+        out[idx + 3] = read_count1_value(bits, y);
+        idx += 4;
+    }
+
+    Ok(out)
+}
+
+/// Decodes every granule/channel's scalefactors and 576 quantized lines
+/// from a frame's assembled main-data buffer (see `reservoir`), returning
+/// `spectrum[granule][channel]`. MPEG-2/2.5 carry a single granule and no
+/// `scfsi`, so the granule-1 reuse path is simply never taken for them.
+///
+/// Returns an error if a channel selects a Huffman table this decoder
+/// doesn't bundle (see `decode_spectrum`), and panics on an MPEG-2/2.5
+/// `scalefac_compress` value (see `sideinfo::scalefac_lengths`).
+pub fn decode_main_data(side_info: &mut SideInfo, main_data: &[u8]) -> Result<Vec<Vec<[i32; 576]>>> {
+    let mut bits = BitReader::new(main_data);
+    let channels = side_info.granule[0].channels.len();
+    let is_v1 = side_info.is_v1;
+    let mut granule0_long = vec![[0u8; 21]; channels];
+    let granules = side_info.granule.len();
+    let mut spectra = Vec::with_capacity(granules);
+
+    for granule_index in 0..granules {
+        let mut granule_spectra = Vec::with_capacity(channels);
+        // Indexes three independent collections (`scfsi`, `granule0_long`,
+        // `side_info.granule[..].channels`) and writes back into
+        // `granule0_long` later in the loop body, so an iterator adapter
+        // over just one of them would still need the index anyway.
+        #[allow(clippy::needless_range_loop)]
+        for ch in 0..channels {
+            let start_bit = bits.bit_pos();
+            let scfsi = side_info.scfsi[ch];
+            let prev_long = granule0_long[ch];
+            let channel = &mut side_info.granule[granule_index].channels[ch];
+            sideinfo::decode_channel_scalefactors(&mut bits, channel, granule_index, scfsi, &prev_long, is_v1);
+            if granule_index == 0 {
+                if let ScaleFactors::Long(long) = &channel.scalefactors {
+                    granule0_long[ch] = *long;
+                }
+            }
+            let end_bit = start_bit + channel.part2_3_length as usize;
+            granule_spectra.push(decode_spectrum(&mut bits, channel, end_bit)?);
+        }
+        spectra.push(granule_spectra);
+    }
+
+    Ok(spectra)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn table_1_matches_the_published_iso_lengths() {
+        let table = HuffTable::table_1();
+        let lens: Vec<u32> = [(0u8, 0u8), (0, 1), (1, 0), (1, 1)]
+            .iter()
+            .map(|&(x, y)| table.codes.iter().find(|(_, &xy)| xy == (x, y)).unwrap().0 .0)
+            .collect();
+        assert_eq!(lens, vec![1, 3, 2, 3]);
+    }
+
+    #[test]
+    fn count1_table_b_is_the_identity_mapping() {
+        let table = Count1Table::fixed();
+        for i in 0u32..16 {
+            let vwxy = (i & 0b1000 != 0, i & 0b0100 != 0, i & 0b0010 != 0, i & 0b0001 != 0);
+            assert_eq!(table.codes[&(4, i)], vwxy);
+        }
+    }
+
+    /// The real tables this decoder ships (table 1 and count1 table B) must
+    /// each form a valid, complete prefix code: every codeword is unique,
+    /// and no codeword is a prefix of another (Kraft's inequality holds
+    /// with equality).
+    #[test]
+    fn the_real_tables_are_valid_complete_prefix_codes() {
+        assert_kraft_equality(HUFFMAN_1.codes.keys().map(|&(len, _)| len));
+        assert_no_codeword_is_a_prefix_of_another(HUFFMAN_1.codes.keys().copied());
+        assert_kraft_equality(COUNT1_TABLE_B.codes.keys().map(|&(len, _)| len));
+        assert_no_codeword_is_a_prefix_of_another(COUNT1_TABLE_B.codes.keys().copied());
+    }
+
+    #[test]
+    fn decode_spectrum_rejects_unimplemented_big_values_tables() {
+        let channel = test_channel(2, false, 1);
+        let mut bits = BitReader::new(&[0u8; 8]);
+        let err = decode_spectrum(&mut bits, &channel, 64).unwrap_err();
+        assert!(err.to_string().contains("big-values Huffman table 2 is not implemented"));
+    }
+
+    #[test]
+    fn decode_spectrum_rejects_unimplemented_count1_table() {
+        let channel = test_channel(1, false, 0);
+        let mut bits = BitReader::new(&[0u8; 8]);
+        let err = decode_spectrum(&mut bits, &channel, 64).unwrap_err();
+        assert!(err.to_string().contains("count1 Huffman table A is not implemented"));
+    }
+
+    fn test_channel(table_select: u8, count1table_select: bool, big_values: u16) -> Channel {
+        Channel {
+            part2_3_length: 0,
+            big_values,
+            global_gain: 0,
+            scalefac_compress: 0,
+            preemphasis: false,
+            scalefac_scale: false,
+            count1table_select,
+            table_select: [table_select; 3],
+            region_0_count: 0,
+            region_1_count: 0,
+            block_type: BlockType::Normal,
+            subblock_gain: [0; 3],
+            scalefactors: ScaleFactors::Long([0; 21]),
+        }
+    }
+
+    fn assert_kraft_equality(lengths: impl Iterator<Item = u32>) {
+        let sum: f64 = lengths.map(|len| 2f64.powi(-(len as i32))).sum();
+        assert!((sum - 1.0).abs() < 1e-9, "Kraft sum {} != 1.0", sum);
+    }
+
+    fn assert_no_codeword_is_a_prefix_of_another(codes: impl Iterator<Item = (u32, u32)>) {
+        let codes: Vec<(u32, u32)> = codes.collect();
+        for &(len_a, code_a) in &codes {
+            for &(len_b, code_b) in &codes {
+                if len_a < len_b {
+                    assert_ne!(code_a, code_b >> (len_b - len_a), "codeword collision/prefix");
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,301 @@
+#[macro_use]
+extern crate lazy_static;
+
+pub mod bitstream;
+pub mod dsp;
+pub mod frames;
+pub mod huffman;
+pub mod reservoir;
+pub mod sideinfo;
+pub mod vbr;
+
+use std::fs::{File};
+use std::io::{Result, BufReader};
+use std::time::Duration;
+
+use dsp::{decode_frame_pcm, requantize, ChannelState};
+use frames::Mp3Frames;
+use huffman::decode_main_data;
+use reservoir::Reservoir;
+use sideinfo::{granule_count, new_side_info};
+use vbr::BitrateKind;
+
+static BITRATE_MAP: [[u16;3];15] = [
+    // Layer 1, 2, 3
+    [0, 0, 0],
+    [32, 32, 32],
+    [64, 48, 40],
+    [96, 56, 48],
+    [128, 64, 56],
+    [160, 80, 64],
+    [192, 96, 80],
+    [224, 112, 96],
+    [256, 128, 112],
+    [288, 160, 128],
+    [320, 192, 160],
+    [352, 224, 192],
+    [384, 256, 224],
+    [416, 320, 256],
+    [448, 384, 320],
+];
+
+// Sampling rate, by MPEG version and sampling-frequency index.
+static SAMPLING_FREQ_MAP: [[u16;3];3] = [
+    [44100, 48000, 32000], // V1
+    [22050, 24000, 16000], // V2
+    [11025, 12000, 8000],  // V2.5
+];
+
+fn sampling_freq(version: &MpegVersion, index: usize) -> u16 {
+    let row = match version {
+        MpegVersion::V1 => 0,
+        MpegVersion::V2 => 1,
+        MpegVersion::V2_5 => 2,
+    };
+    SAMPLING_FREQ_MAP[row][index]
+}
+
+/// `floor(samples_per_frame/8 * bitrate_bps / samplerate) + padding`, i.e.
+/// 144000*bitrate/samplerate for MPEG-1 Layer III (1152 samples/frame) and
+/// 72000*bitrate/samplerate for MPEG-2/2.5 Layer III (576 samples/frame).
+fn frame_size(version: &MpegVersion, bitrate: u16, sampling_freq: u16, padding: bool) -> usize {
+    let coefficient = match version {
+        MpegVersion::V1 => 144_000,
+        MpegVersion::V2 | MpegVersion::V2_5 => 72_000,
+    };
+    let mut size = coefficient * bitrate as usize / sampling_freq as usize;
+    if padding {
+        size += 1;
+    }
+    size
+}
+
+#[derive(Debug)]
+pub enum Mode {
+    Stereo,
+    JointStereo,
+    DualMonaural,
+    SingleChannel,
+}
+
+#[derive(Debug)]
+pub enum Layer {
+    Reserved,
+    L1,
+    L2,
+    L3,
+}
+
+#[derive(Debug)]
+pub enum MpegVersion {
+    V1,
+    V2,
+    V2_5,
+}
+
+#[derive(Debug)]
+pub struct Mp3 {
+    duration: Duration,
+    bitrate_kind: BitrateKind,
+}
+
+impl Mp3 {
+    pub fn duration(&self) -> Duration {
+        self.duration
+    }
+
+    pub fn bitrate_kind(&self) -> BitrateKind {
+        self.bitrate_kind
+    }
+}
+
+#[derive(Debug)]
+pub struct FrameHeader {
+    pub id            : MpegVersion,
+    pub layer         : Layer,
+    pub protection    : bool,
+    pub bitrate       : u16,
+    pub sampling_freq : u16,
+    pub padding       : bool,
+    pub mode          : Mode,
+    pub i_stereo      : bool,
+    pub ms_stereo     : bool,
+    pub copyright     : bool,
+    pub original      : bool,
+    pub emphasis      : u8,
+    pub size          : usize,
+}
+
+impl FrameHeader {
+    pub fn single_channel(&self) -> bool {
+        matches!(self.mode, Mode::SingleChannel)
+    }
+}
+
+#[derive(Debug)]
+pub struct Frame {
+    pub header: FrameHeader,
+    pub body: Vec<u8>,
+    /// `Some(true)`/`Some(false)` for protected frames, `None` otherwise.
+    /// See `frames::Mp3Frames::with_crc_mode` to skip or error on mismatches.
+    pub crc_ok: Option<bool>,
+}
+
+pub(crate) fn has_sync_word(frame_header: &[u8;4]) -> bool {
+    frame_header[0] == 0b11111111 && frame_header[1] & 0b11100000 == 0b11100000
+}
+
+/// Builds a `FrameHeader` from an already-synced 4-byte header (see
+/// `frames::Mp3Frames`, which is responsible for locating those 4 bytes).
+pub(crate) fn new_frame_header(frame_header: &[u8;4]) -> FrameHeader {
+    let id = match (frame_header[1] & 0b00011000) >> 3 {
+        0 => MpegVersion::V2_5,
+        2 => MpegVersion::V2,
+        3 => MpegVersion::V1,
+        x => panic!("{} is not supported MPEG version ID", x),
+    };
+    let layer = match (frame_header[1] & 0b00000110) >> 1 {
+        0 => Layer::Reserved,
+        1 => Layer::L3,
+        2 => Layer::L2,
+        3 => Layer::L1,
+        x => panic!("{} is not supported layer", x),
+    };
+    // 0: CRC
+    // 1: No CRC
+    let protection = frame_header[1] & 0b00000001 != 0b00000001;
+
+    let bitrate_index = ((frame_header[2] & 0b11110000) >> 4) as usize;
+    let layer_index = match layer {
+        Layer::L3 => 2,
+        Layer::L2 => 1,
+        Layer::L1 => 0,
+        _ => panic!("Layer::Reserved is not supported"),
+    };
+    let bitrate = BITRATE_MAP[bitrate_index][layer_index];
+
+    let sampling_freq_index = ((frame_header[2] & 0b00001100) >> 2) as usize;
+    let sampling_freq = sampling_freq(&id, sampling_freq_index);
+
+    let padding = frame_header[2] & 0b00000010 == 0b00000010;
+    let mode = match (frame_header[3] & 0b11000000) >> 6 {
+        0 => Mode::Stereo,
+        1 => Mode::JointStereo,
+        2 => Mode::DualMonaural,
+        3 => Mode::SingleChannel,
+        x => panic!("{} is not supported mode", x),
+    };
+    let i_stereo = frame_header[3] & 0b00010000 == 0b00010000;
+    let ms_stereo = frame_header[3] & 0b00100000 == 0b00100000;
+    let copyright = frame_header[3] & 0b00001000 == 0b00001000;
+    let original = frame_header[3] & 0b00000100 == 0b00000100;
+    let emphasis = frame_header[3] & 0b00000011;
+    let size = frame_size(&id, bitrate, sampling_freq, padding);
+    FrameHeader {
+        id,
+        layer,
+        protection,
+        bitrate,
+        sampling_freq,
+        padding,
+        mode,
+        i_stereo,
+        ms_stereo,
+        copyright,
+        original,
+        emphasis,
+        size,
+    }
+}
+
+/// Walks every frame of `file_path`, decoding each one to PCM in turn. The
+/// bit reservoir and per-channel IMDCT/filterbank state are carried across
+/// frames, same as a real player would.
+///
+/// Along the way this also works out playback duration: if the first frame
+/// carries a Xing/Info or VBRI header, its frame count gives an exact VBR
+/// duration without needing the rest of the scan; otherwise the duration
+/// falls back to the CBR frame count this same decode pass already counts.
+pub fn open(file_path: &str) -> Result<Mp3> {
+    let file = BufReader::new(File::open(file_path)?);
+    let mut reservoir = Reservoir::new();
+    let mut state: Option<Vec<ChannelState>> = None;
+
+    let mut frame_count: u64 = 0;
+    let mut samples_per_frame = 0usize;
+    let mut sampling_freq = 0u16;
+    let mut vbr_frame_count: Option<u64> = None;
+
+    for frame in Mp3Frames::new(file) {
+        let frame = frame?;
+        let header = &frame.header;
+        if frame.crc_ok == Some(false) {
+            println!("{:?}: CRC mismatch, decoding anyway", frame.header.id);
+        }
+        let crc_len = if header.protection { 2 } else { 0 };
+        let side_info_len = sideinfo::side_info_len(&header.id, header.single_channel());
+
+        if frame_count == 0 {
+            samples_per_frame = sideinfo::samples_per_frame(&header.id);
+            sampling_freq = header.sampling_freq;
+            vbr_frame_count =
+                vbr::detect(&frame.body, crc_len + side_info_len).and_then(|tag| tag.frame_count).map(u64::from);
+        }
+        frame_count += 1;
+
+        if frame.body.len() < crc_len + side_info_len {
+            println!("{:?}", frame);
+            continue;
+        }
+
+        let side_bytes = &frame.body[crc_len..crc_len + side_info_len];
+        let mut side = new_side_info(side_bytes, header);
+        let main_data_len = reservoir::main_data_len(header, side_info_len);
+        let main_data = &frame.body[frame.body.len() - main_data_len..];
+        let assembled = reservoir.assemble(side.main_data_begin, main_data);
+        let spectrum = decode_main_data(&mut side, &assembled)?;
+
+        let channels = side.granule[0].channels.len();
+        let granules = granule_count(&header.id);
+        let channel_state = state.get_or_insert_with(Vec::new);
+        if channel_state.len() != channels {
+            *channel_state = (0..channels).map(|_| ChannelState::new()).collect();
+        }
+        let spectra: Vec<Vec<[f32; 576]>> = (0..channels)
+            .map(|ch| {
+                (0..granules)
+                    .map(|g| requantize(&side.granule[g].channels[ch], &spectrum[g][ch]))
+                    .collect()
+            })
+            .collect();
+        let pcm = decode_frame_pcm(&side.granule, &spectra, channel_state);
+        println!("decoded {} channel(s) of {} PCM samples", pcm.len(), pcm.first().map_or(0, |c| c.len()));
+
+        println!("{:?}", frame);
+    }
+
+    let (total_frames, bitrate_kind) = match vbr_frame_count {
+        Some(frames) => (frames, BitrateKind::Vbr),
+        None => (frame_count, BitrateKind::Cbr),
+    };
+    let duration = if sampling_freq > 0 {
+        Duration::from_secs_f64(total_frames as f64 * samples_per_frame as f64 / sampling_freq as f64)
+    } else {
+        Duration::default()
+    };
+
+    Ok(Mp3 { duration, bitrate_kind })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn new_frame_header_reads_sampling_freq_index_from_bits_2_and_3() {
+        // MPEG-1, Layer III, no protection, 128kbps, 44100Hz, no padding,
+        // stereo: 0xFF 0xFB 0x90 0x00.
+        let header = new_frame_header(&[0xFF, 0xFB, 0x90, 0x00]);
+        assert_eq!(header.sampling_freq, 44100);
+    }
+}
@@ -0,0 +1,138 @@
+use crate::FrameHeader;
+
+/// MPEG-1 only ever needs to look back as far as `main_data_begin` can
+/// reach (9 bits => up to 511 bytes), so a small rolling buffer is enough.
+const DEFAULT_CAP: usize = 512;
+
+/// Bit-reservoir buffer used to reassemble a frame's Layer III main data.
+///
+/// Layer III allows a frame to borrow unused bits from the tail end of
+/// previous frames, so the Huffman-coded main data for a given frame does
+/// not necessarily start where that frame's side info ends. `main_data_begin`
+/// (from `SideInfo`) says how many bytes to back up into the reservoir
+/// before appending the current frame's own main-data bytes.
+pub struct Reservoir {
+    buf: Vec<u8>,
+    cap: usize,
+}
+
+impl Reservoir {
+    pub fn new() -> Self {
+        Reservoir {
+            buf: Vec::with_capacity(DEFAULT_CAP),
+            cap: DEFAULT_CAP,
+        }
+    }
+
+    /// Assembles the decode buffer for a frame: the last `main_data_begin`
+    /// bytes still held in the reservoir, followed by this frame's own
+    /// main-data bytes. A `main_data_begin` of 0 means the frame's main
+    /// data starts exactly at `frame_data`, with nothing borrowed.
+    pub fn assemble(&mut self, main_data_begin: usize, frame_data: &[u8]) -> Vec<u8> {
+        let borrow = main_data_begin.min(self.buf.len());
+        let start = self.buf.len() - borrow;
+        let mut assembled = Vec::with_capacity(borrow + frame_data.len());
+        assembled.extend_from_slice(&self.buf[start..]);
+        assembled.extend_from_slice(frame_data);
+
+        self.buf.extend_from_slice(frame_data);
+        if self.buf.len() > self.cap {
+            let excess = self.buf.len() - self.cap;
+            self.buf.drain(0..excess);
+        }
+
+        assembled
+    }
+
+    /// Adapts a sequence of `(main_data_begin, frame_data)` pairs (one per
+    /// frame, in stream order) into an iterator of assembled main-data
+    /// buffers, threading reservoir state across calls the same way calling
+    /// `assemble` in a loop would.
+    pub fn assemble_frames<'a, I>(&'a mut self, frames: I) -> impl Iterator<Item = Vec<u8>> + 'a
+    where
+        I: IntoIterator<Item = (usize, Vec<u8>)>,
+        I::IntoIter: 'a,
+    {
+        frames.into_iter().map(move |(main_data_begin, frame_data)| self.assemble(main_data_begin, &frame_data))
+    }
+}
+
+impl Default for Reservoir {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Length, in bytes, of a frame's main-data region: everything after the
+/// 4-byte header, the (optional) 2-byte CRC and the side info.
+pub fn main_data_len(header: &FrameHeader, side_info_len: usize) -> usize {
+    let crc_len = if header.protection { 2 } else { 0 };
+    header.size - 4 - side_info_len - crc_len
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Layer, Mode, MpegVersion};
+
+    fn header(size: usize, protection: bool) -> FrameHeader {
+        FrameHeader {
+            id: MpegVersion::V1,
+            layer: Layer::L3,
+            protection,
+            bitrate: 128,
+            sampling_freq: 44100,
+            padding: false,
+            mode: Mode::Stereo,
+            i_stereo: false,
+            ms_stereo: false,
+            copyright: false,
+            original: false,
+            emphasis: 0,
+            size,
+        }
+    }
+
+    #[test]
+    fn main_data_len_subtracts_header_crc_and_side_info() {
+        assert_eq!(main_data_len(&header(417, false), 32), 381);
+        assert_eq!(main_data_len(&header(417, true), 32), 379);
+    }
+
+    #[test]
+    fn assemble_with_no_history_borrows_nothing() {
+        let mut reservoir = Reservoir::new();
+        assert_eq!(reservoir.assemble(0, &[1, 2, 3]), vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn assemble_borrows_tail_of_prior_frames() {
+        let mut reservoir = Reservoir::new();
+        reservoir.assemble(0, &[1, 2, 3, 4]);
+        let assembled = reservoir.assemble(2, &[5, 6]);
+        assert_eq!(assembled, vec![3, 4, 5, 6]);
+    }
+
+    #[test]
+    fn assemble_clamps_borrow_to_reservoir_len() {
+        let mut reservoir = Reservoir::new();
+        let assembled = reservoir.assemble(100, &[1, 2]);
+        assert_eq!(assembled, vec![1, 2]);
+    }
+
+    #[test]
+    fn assemble_truncates_reservoir_to_cap() {
+        let mut reservoir = Reservoir::new();
+        reservoir.assemble(0, &vec![0xAB; DEFAULT_CAP + 100]);
+        assert_eq!(reservoir.buf.len(), DEFAULT_CAP);
+        assert_eq!(reservoir.buf, vec![0xAB; DEFAULT_CAP]);
+    }
+
+    #[test]
+    fn assemble_frames_iterator_threads_state_across_calls() {
+        let mut reservoir = Reservoir::new();
+        let frames = vec![(0usize, vec![1, 2, 3, 4]), (2usize, vec![5, 6])];
+        let assembled: Vec<Vec<u8>> = reservoir.assemble_frames(frames).collect();
+        assert_eq!(assembled, vec![vec![1, 2, 3, 4], vec![3, 4, 5, 6]]);
+    }
+}
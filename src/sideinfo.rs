@@ -0,0 +1,301 @@
+use crate::bitstream::BitReader;
+use crate::{FrameHeader, MpegVersion};
+
+/// `(slen1, slen2)` bits-per-scalefactor pairs indexed by `scalefac_compress`
+/// (MPEG-1 only). The first 11 long scalefactor bands use `slen1`, the
+/// remaining 10 use `slen2`; short blocks split their 12 bands the same way.
+const SCALEFAC_COMPRESS_TABLE: [(u32, u32); 16] = [
+    (0, 0), (0, 1), (0, 2), (0, 3),
+    (3, 0), (1, 1), (1, 2), (1, 3),
+    (2, 1), (2, 2), (2, 3), (3, 1),
+    (3, 2), (3, 3), (4, 2), (4, 3),
+];
+
+/// sfb ranges of the four groups `scfsi` can mark as "reuse granule 0".
+/// MPEG-2/2.5 have no `scfsi` (they only ever carry one granule).
+const SCFSI_GROUPS: [(usize, usize); 4] = [(0, 6), (6, 11), (11, 16), (16, 21)];
+
+/// Side info is 17/32 bytes (mono/stereo) for MPEG-1, 9/17 bytes for
+/// MPEG-2 and MPEG-2.5, which carry only one granule and a narrower
+/// `main_data_begin`/`scalefac_compress`.
+pub fn side_info_len(version: &MpegVersion, single_channel: bool) -> usize {
+    match (version, single_channel) {
+        (MpegVersion::V1, true) => 17,
+        (MpegVersion::V1, false) => 32,
+        (_, true) => 9,
+        (_, false) => 17,
+    }
+}
+
+pub fn granule_count(version: &MpegVersion) -> usize {
+    match version {
+        MpegVersion::V1 => 2,
+        MpegVersion::V2 | MpegVersion::V2_5 => 1,
+    }
+}
+
+/// PCM samples per channel produced by one frame: 1152 for MPEG-1 Layer III
+/// (two 576-line granules), 576 for MPEG-2/2.5 (one granule).
+pub fn samples_per_frame(version: &MpegVersion) -> usize {
+    granule_count(version) * 576
+}
+
+#[derive(Debug)]
+pub struct SideInfo {
+    pub main_data_begin: usize,
+    pub scfsi: [u8; 2],
+    pub granule: Vec<Granule>,
+    pub is_v1: bool,
+}
+
+#[derive(Debug)]
+pub struct Granule {
+    pub channels: Vec<Channel>,
+}
+
+#[derive(Debug)]
+pub struct Channel {
+    pub part2_3_length: u16,
+    pub big_values: u16,
+    pub global_gain: u8,
+    pub scalefac_compress: u16,
+    pub preemphasis: bool,
+    pub scalefac_scale: bool,
+    pub count1table_select: bool,
+    // windows_switching_flag == 0
+    pub table_select: [u8; 3],
+    pub region_0_count: u8,
+    pub region_1_count: u8,
+    // windows_switching_flag == 1
+    pub block_type: BlockType,
+    pub subblock_gain: [u8; 3],
+    pub scalefactors: ScaleFactors,
+}
+
+#[derive(Debug)]
+pub enum BlockType {
+    Normal,
+    Start,
+    Short,
+    Mixed,
+    End,
+}
+
+/// Decoded scalefactors, shaped according to the granule's block type.
+#[derive(Debug)]
+pub enum ScaleFactors {
+    Long([u8; 21]),
+    Short([[u8; 3]; 12]),
+    Mixed { long: [u8; 8], short: [[u8; 3]; 9] },
+}
+
+/// Parses a frame's already-extracted side-info bytes (see `open`, which
+/// slices them out of the frame body ahead of the main-data region).
+pub fn new_side_info(side: &[u8], header: &FrameHeader) -> SideInfo {
+    let channels = if header.single_channel() { 1 } else { 2 };
+    parse_side_info(side, channels, &header.id)
+}
+
+fn parse_side_info(side: &[u8], channels: usize, version: &MpegVersion) -> SideInfo {
+    let is_v1 = matches!(version, MpegVersion::V1);
+    let mut bits = BitReader::new(side);
+    let main_data_begin = bits.read_bits(if is_v1 { 9 } else { 8 }) as usize;
+    // private_bits, not used by the decoder.
+    let private_bits = match (is_v1, channels) {
+        (true, 1) => 5,
+        (true, _) => 3,
+        (false, 1) => 1,
+        (false, _) => 2,
+    };
+    bits.read_bits(private_bits);
+
+    let mut scfsi = [0u8; 2];
+    if is_v1 {
+        for slot in scfsi.iter_mut().take(channels) {
+            *slot = bits.read_bits(4) as u8;
+        }
+    }
+
+    let granule = (0..granule_count(version))
+        .map(|_| Granule {
+            channels: (0..channels).map(|_| new_channel(&mut bits, is_v1)).collect(),
+        })
+        .collect();
+
+    SideInfo { main_data_begin, scfsi, granule, is_v1 }
+}
+
+// 59 bits per channel for MPEG-1; MPEG-2/2.5 carry a 9-bit
+// `scalefac_compress` instead of 4 bits and no preemphasis flag, 63 bits
+// (one granule, no scfsi).
+fn new_channel(bits: &mut BitReader, is_v1: bool) -> Channel {
+    let part2_3_length = bits.read_bits(12) as u16;
+    let big_values = bits.read_bits(9) as u16;
+    let global_gain = bits.read_bits(8) as u8;
+    let scalefac_compress = bits.read_bits(if is_v1 { 4 } else { 9 }) as u16;
+    let window_switching_flag = bits.read_bit();
+
+    let (block_type, table_select, region_0_count, region_1_count, subblock_gain) =
+        if window_switching_flag {
+            let raw_block_type = bits.read_bits(2);
+            // mixed_block_flag sits at a fixed bit position regardless of
+            // block_type, so it must always be read to stay aligned.
+            let mixed_block_flag = bits.read_bit();
+            let block_type = match raw_block_type {
+                1 => BlockType::Start,
+                2 if mixed_block_flag => BlockType::Mixed,
+                2 => BlockType::Short,
+                3 => BlockType::End,
+                x => panic!("{} is not a supported block type", x),
+            };
+            let table_select = [bits.read_bits(5) as u8, bits.read_bits(5) as u8, 0];
+            let subblock_gain = [
+                bits.read_bits(3) as u8,
+                bits.read_bits(3) as u8,
+                bits.read_bits(3) as u8,
+            ];
+            (block_type, table_select, 0, 0, subblock_gain)
+        } else {
+            let table_select = [
+                bits.read_bits(5) as u8,
+                bits.read_bits(5) as u8,
+                bits.read_bits(5) as u8,
+            ];
+            let region_0_count = bits.read_bits(4) as u8;
+            let region_1_count = bits.read_bits(3) as u8;
+            (BlockType::Normal, table_select, region_0_count, region_1_count, [0; 3])
+        };
+
+    let preemphasis = is_v1 && bits.read_bit();
+    let scalefac_scale = bits.read_bit();
+    let count1table_select = bits.read_bit();
+
+    Channel {
+        part2_3_length,
+        big_values,
+        global_gain,
+        scalefac_compress,
+        block_type,
+        table_select,
+        region_0_count,
+        region_1_count,
+        preemphasis,
+        scalefac_scale,
+        count1table_select,
+        subblock_gain,
+        // Filled in by `decode_channel_scalefactors` once the main-data buffer for
+        // this frame has been assembled.
+        scalefactors: ScaleFactors::Long([0; 21]),
+    }
+}
+
+/// Decodes one granule/channel's scalefactors from `bits` (positioned at
+/// the start of that channel's slice of the assembled main-data buffer,
+/// see `reservoir`), honouring `scfsi` reuse of granule 0's long-block
+/// scalefactors in granule 1. Leaves `bits` positioned at the start of
+/// this channel's Huffman-coded big-values/count1 data.
+pub fn decode_channel_scalefactors(
+    bits: &mut BitReader,
+    channel: &mut Channel,
+    granule_index: usize,
+    scfsi: u8,
+    prev_long: &[u8; 21],
+    is_v1: bool,
+) {
+    let (slen1, slen2) = scalefac_lengths(channel.scalefac_compress, is_v1);
+    channel.scalefactors =
+        decode_block_scalefactors(bits, &channel.block_type, slen1, slen2, granule_index, scfsi, prev_long);
+}
+
+/// `(slen1, slen2)` for a given MPEG-1 `scalefac_compress` value (0..=15),
+/// via the exact standard table.
+///
+/// MPEG-2/2.5's 9-bit `scalefac_compress` instead picks from a larger,
+/// block-type-dependent `nsfb`/`slen` table (ISO/IEC 13818-3 Annex B,
+/// Table B.8) that this decoder does not implement, so it is rejected
+/// rather than decoded against the wrong bit widths. The reject has to key
+/// off `is_v1`, not the value itself: MPEG-2/2.5's 9-bit field can land
+/// under 16 just as easily as over it, and would otherwise be silently
+/// decoded against the MPEG-1 table.
+fn scalefac_lengths(scalefac_compress: u16, is_v1: bool) -> (u32, u32) {
+    if is_v1 {
+        SCALEFAC_COMPRESS_TABLE[scalefac_compress as usize]
+    } else {
+        panic!("MPEG-2/2.5 scalefactor decoding is not supported (scalefac_compress = {})", scalefac_compress);
+    }
+}
+
+fn decode_block_scalefactors(
+    bits: &mut BitReader,
+    block_type: &BlockType,
+    slen1: u32,
+    slen2: u32,
+    granule_index: usize,
+    scfsi: u8,
+    prev_long: &[u8; 21],
+) -> ScaleFactors {
+    match block_type {
+        BlockType::Short => {
+            let mut sf = [[0u8; 3]; 12];
+            for (band, windows) in sf.iter_mut().enumerate() {
+                let len = if band < 6 { slen1 } else { slen2 };
+                for window in windows.iter_mut() {
+                    *window = bits.read_bits(len) as u8;
+                }
+            }
+            ScaleFactors::Short(sf)
+        }
+        BlockType::Mixed => {
+            let mut long = [0u8; 8];
+            for slot in long.iter_mut() {
+                *slot = bits.read_bits(slen1) as u8;
+            }
+            let mut short = [[0u8; 3]; 9];
+            for (band, windows) in short.iter_mut().enumerate() {
+                let len = if band < 3 { slen1 } else { slen2 };
+                for window in windows.iter_mut() {
+                    *window = bits.read_bits(len) as u8;
+                }
+            }
+            ScaleFactors::Mixed { long, short }
+        }
+        BlockType::Normal | BlockType::Start | BlockType::End => {
+            let mut sf = [0u8; 21];
+            for (group, &(start, end)) in SCFSI_GROUPS.iter().enumerate() {
+                // scfsi bit 3 (MSB) is group 0, bit 0 is group 3.
+                let reuse = granule_index == 1 && scfsi & (1 << (3 - group)) != 0;
+                let len = if start < 11 { slen1 } else { slen2 };
+                for sfb in start..end {
+                    sf[sfb] = if reuse { prev_long[sfb] } else { bits.read_bits(len) as u8 };
+                }
+            }
+            ScaleFactors::Long(sf)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scalefac_lengths_uses_the_mpeg1_table() {
+        assert_eq!(scalefac_lengths(0, true), (0, 0));
+        assert_eq!(scalefac_lengths(4, true), (3, 0));
+        assert_eq!(scalefac_lengths(15, true), (4, 3));
+    }
+
+    #[test]
+    #[should_panic(expected = "MPEG-2/2.5 scalefactor decoding is not supported")]
+    fn scalefac_lengths_rejects_mpeg2_regardless_of_value() {
+        scalefac_lengths(16, false);
+    }
+
+    #[test]
+    #[should_panic(expected = "MPEG-2/2.5 scalefactor decoding is not supported")]
+    fn scalefac_lengths_rejects_mpeg2_even_when_the_value_looks_like_an_mpeg1_one() {
+        // MPEG-2/2.5's 9-bit scalefac_compress can land under 16 just as
+        // easily as over it; the version, not the value, decides rejection.
+        scalefac_lengths(4, false);
+    }
+}
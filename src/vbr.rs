@@ -0,0 +1,147 @@
+//! Xing/Info and VBRI header detection, used to compute a stream's exact
+//! duration from its first frame instead of decoding every frame in it.
+
+/// VBRI sits at a fixed offset, 32 bytes past the end of the 4-byte frame
+/// header; `body` starts right after that header, so the tag is 32 bytes
+/// into `body`.
+const VBRI_BODY_OFFSET: usize = 32;
+
+const XING_FLAG_FRAMES: u32 = 0x1;
+const XING_FLAG_BYTES: u32 = 0x2;
+
+/// Whether a duration was computed from a per-frame scan or read exactly
+/// off a VBR header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BitrateKind {
+    Cbr,
+    Vbr,
+}
+
+/// Frame/byte totals recovered from a Xing/Info or VBRI header.
+#[derive(Debug, Clone, Copy)]
+pub struct VbrTag {
+    pub frame_count: Option<u32>,
+    pub byte_count: Option<u32>,
+}
+
+/// Looks for a Xing/Info tag (living where the first frame's main data
+/// would otherwise start) or, failing that, a VBRI tag (a fixed offset
+/// past the frame header). `main_data_offset` is `crc_len + side_info_len`.
+pub fn detect(body: &[u8], main_data_offset: usize) -> Option<VbrTag> {
+    parse_xing(body, main_data_offset).or_else(|| parse_vbri(body))
+}
+
+fn parse_xing(body: &[u8], offset: usize) -> Option<VbrTag> {
+    let magic = body.get(offset..offset + 4)?;
+    if magic != b"Xing" && magic != b"Info" {
+        return None;
+    }
+    let flags = be_u32(body, offset + 4)?;
+    let mut field = offset + 8;
+    let frame_count = if flags & XING_FLAG_FRAMES != 0 {
+        let v = be_u32(body, field);
+        field += 4;
+        v
+    } else {
+        None
+    };
+    let byte_count = if flags & XING_FLAG_BYTES != 0 { be_u32(body, field) } else { None };
+    Some(VbrTag { frame_count, byte_count })
+}
+
+fn parse_vbri(body: &[u8]) -> Option<VbrTag> {
+    let magic = body.get(VBRI_BODY_OFFSET..VBRI_BODY_OFFSET + 4)?;
+    if magic != b"VBRI" {
+        return None;
+    }
+    // "VBRI" + version(2) + delay(2) + quality(2) + byte_count(4) + frame_count(4).
+    let byte_count = be_u32(body, VBRI_BODY_OFFSET + 10);
+    let frame_count = be_u32(body, VBRI_BODY_OFFSET + 14);
+    Some(VbrTag { frame_count, byte_count })
+}
+
+fn be_u32(body: &[u8], offset: usize) -> Option<u32> {
+    let bytes = body.get(offset..offset + 4)?;
+    Some(((bytes[0] as u32) << 24) | ((bytes[1] as u32) << 16) | ((bytes[2] as u32) << 8) | bytes[3] as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn be_u32_reads_big_endian() {
+        assert_eq!(be_u32(&[0x00, 0x00, 0x01, 0x00], 0), Some(256));
+    }
+
+    #[test]
+    fn be_u32_is_none_past_the_end() {
+        assert_eq!(be_u32(&[0x00, 0x00], 0), None);
+    }
+
+    #[test]
+    fn parse_xing_reads_frame_and_byte_counts_when_both_flags_are_set() {
+        let mut body = vec![0u8; 8];
+        body.extend_from_slice(b"Xing");
+        body.extend_from_slice(&[0x00, 0x00, 0x00, 0x03]); // flags: frames + bytes
+        body.extend_from_slice(&[0x00, 0x00, 0x01, 0x00]); // frame_count = 256
+        body.extend_from_slice(&[0x00, 0x00, 0x20, 0x00]); // byte_count = 8192
+
+        let tag = parse_xing(&body, 8).expect("Xing tag found");
+        assert_eq!(tag.frame_count, Some(256));
+        assert_eq!(tag.byte_count, Some(8192));
+    }
+
+    #[test]
+    fn parse_xing_recognizes_info_tag_too() {
+        let mut body = vec![0u8; 8];
+        body.extend_from_slice(b"Info");
+        body.extend_from_slice(&[0x00, 0x00, 0x00, 0x00]); // no flags set
+        assert!(parse_xing(&body, 8).is_some());
+    }
+
+    #[test]
+    fn parse_xing_skips_fields_whose_flag_is_unset() {
+        let mut body = vec![0u8; 8];
+        body.extend_from_slice(b"Xing");
+        body.extend_from_slice(&[0x00, 0x00, 0x00, 0x01]); // flags: frames only
+        body.extend_from_slice(&[0x00, 0x00, 0x00, 0x2A]); // frame_count = 42
+
+        let tag = parse_xing(&body, 8).expect("Xing tag found");
+        assert_eq!(tag.frame_count, Some(42));
+        assert_eq!(tag.byte_count, None);
+    }
+
+    #[test]
+    fn parse_xing_returns_none_without_the_magic() {
+        let body = vec![0u8; 16];
+        assert!(parse_xing(&body, 8).is_none());
+    }
+
+    #[test]
+    fn parse_vbri_reads_the_fixed_offset_tag() {
+        let mut body = vec![0u8; VBRI_BODY_OFFSET];
+        body.extend_from_slice(b"VBRI");
+        body.extend_from_slice(&[0x00, 0x01]); // version
+        body.extend_from_slice(&[0x00, 0x00]); // delay
+        body.extend_from_slice(&[0x00, 0x00]); // quality
+        body.extend_from_slice(&[0x00, 0x00, 0x10, 0x00]); // byte_count = 4096
+        body.extend_from_slice(&[0x00, 0x00, 0x00, 0x0A]); // frame_count = 10
+
+        let tag = parse_vbri(&body).expect("VBRI tag found");
+        assert_eq!(tag.frame_count, Some(10));
+        assert_eq!(tag.byte_count, Some(4096));
+    }
+
+    #[test]
+    fn detect_falls_back_to_vbri_when_xing_is_absent() {
+        let mut body = vec![0u8; VBRI_BODY_OFFSET];
+        body.extend_from_slice(b"VBRI");
+        body.extend_from_slice(&[0x00, 0x01, 0x00, 0x00, 0x00, 0x00]);
+        body.extend_from_slice(&[0x00, 0x00, 0x00, 0x64]); // byte_count = 100
+        body.extend_from_slice(&[0x00, 0x00, 0x00, 0x05]); // frame_count = 5
+
+        let tag = detect(&body, 8).expect("VBRI tag found via fallback");
+        assert_eq!(tag.frame_count, Some(5));
+    }
+}